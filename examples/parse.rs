@@ -1,10 +1,13 @@
+extern crate byteorder;
 extern crate clap;
 extern crate mpeg2ts;
 #[macro_use]
 extern crate trackable;
 
 use std::io::Write;
+use byteorder::{BigEndian, WriteBytesExt};
 use clap::{App, Arg};
+use mpeg2ts::es::framing::{AdtsHeaderParams, NalUnits};
 use mpeg2ts::pes::{PesPacketReader, ReadPesPacket};
 use mpeg2ts::ts::{ReadTsPacket, TsPacketReader};
 use trackable::error::Failure;
@@ -15,7 +18,14 @@ fn main() {
             Arg::with_name("OUTPUT_TYPE")
                 .long("output-type")
                 .takes_value(true)
-                .possible_values(&["ts", "pes", "es-audio", "es-video"])
+                .possible_values(&[
+                    "ts",
+                    "pes",
+                    "es-audio",
+                    "es-video",
+                    "es-audio-adts",
+                    "es-video-nal",
+                ])
                 .default_value("ts"),
         )
         .get_matches();
@@ -58,6 +68,48 @@ fn main() {
                 );
             }
         }
+        "es-audio-adts" => {
+            // The AAC configuration (profile/sampling rate/channels) is
+            // normally recovered from the stream's out-of-band
+            // AudioSpecificConfig; this example hard-codes AAC-LC/44.1kHz
+            // stereo since `parse` does not demux the PMT's descriptors.
+            let adts = AdtsHeaderParams {
+                profile: 1,
+                sampling_frequency_index: 4,
+                channel_config: 2,
+            };
+            let mut reader = PesPacketReader::new(TsPacketReader::new(std::io::stdin()));
+            while let Some(packet) = track_try_unwrap!(reader.read_pes_packet()) {
+                if !packet.header.stream_id.is_audio() {
+                    continue;
+                }
+                let stdout = std::io::stdout();
+                let mut stdout = stdout.lock();
+                track_try_unwrap!(adts.write_header(packet.data.len(), &mut stdout));
+                track_try_unwrap!(stdout.write_all(&packet.data).map_err(Failure::from_error));
+            }
+        }
+        "es-video-nal" => {
+            // Each Annex-B NAL unit is written as an MP4-style (AVCC/HVCC)
+            // sample: a 4-byte big-endian length followed by the NAL unit
+            // bytes (start code excluded), per `NalUnits`' doc comment.
+            let mut reader = PesPacketReader::new(TsPacketReader::new(std::io::stdin()));
+            let stdout = std::io::stdout();
+            let mut stdout = stdout.lock();
+            while let Some(packet) = track_try_unwrap!(reader.read_pes_packet()) {
+                if !packet.header.stream_id.is_video() {
+                    continue;
+                }
+                for nal in NalUnits::new(&packet.data) {
+                    track_try_unwrap!(
+                        stdout
+                            .write_u32::<BigEndian>(nal.len() as u32)
+                            .map_err(Failure::from_error)
+                    );
+                    track_try_unwrap!(stdout.write_all(nal).map_err(Failure::from_error));
+                }
+            }
+        }
         _ => unreachable!(),
     }
 }
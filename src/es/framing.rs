@@ -0,0 +1,235 @@
+use std::io::Write;
+use byteorder::WriteBytesExt;
+
+use {ErrorKind, Result};
+use es::StreamType;
+
+/// Parameters needed to synthesize an ADTS header for an AAC access unit.
+///
+/// These come from the stream's `AudioSpecificConfig` (out of band, e.g.
+/// from a descriptor or container-level config), not from the TS/PES
+/// layer itself.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AdtsHeaderParams {
+    /// `profile` field (MPEG-4 Audio Object Type minus one), e.g. `1` for AAC-LC.
+    pub profile: u8,
+    pub sampling_frequency_index: u8,
+    pub channel_config: u8,
+}
+impl AdtsHeaderParams {
+    const HEADER_LEN: usize = 7;
+
+    /// Writes the 7-byte ADTS header for an access unit of `aac_frame_len`
+    /// bytes (the AAC payload only; the header length is added internally).
+    pub fn write_header<W: Write>(&self, aac_frame_len: usize, mut writer: W) -> Result<()> {
+        let frame_len = aac_frame_len + Self::HEADER_LEN;
+        track_assert!(
+            frame_len <= 0x1FFF,
+            ErrorKind::InvalidInput,
+            "AAC frame is too large for an ADTS header: {} bytes",
+            frame_len
+        );
+        track_assert!(
+            self.profile <= 0b11,
+            ErrorKind::InvalidInput,
+            "profile does not fit in 2 bits: {}",
+            self.profile
+        );
+        track_assert!(
+            self.sampling_frequency_index <= 0b1111,
+            ErrorKind::InvalidInput,
+            "sampling_frequency_index does not fit in 4 bits: {}",
+            self.sampling_frequency_index
+        );
+        track_assert!(
+            self.channel_config <= 0b111,
+            ErrorKind::InvalidInput,
+            "channel_config does not fit in 3 bits: {}",
+            self.channel_config
+        );
+
+        track_io!(writer.write_u8(0xFF))?; // syncword (high 8 bits)
+        track_io!(writer.write_u8(0xF1))?; // syncword(low 4 bits) + MPEG-4 + layer=00 + protection_absent=1
+
+        let byte2 = (self.profile << 6)
+            | (self.sampling_frequency_index << 2)
+            | ((self.channel_config >> 2) & 0b1);
+        track_io!(writer.write_u8(byte2))?;
+
+        let byte3 = ((self.channel_config & 0b11) << 6) | ((frame_len >> 11) as u8 & 0b11);
+        track_io!(writer.write_u8(byte3))?;
+
+        let byte4 = ((frame_len >> 3) & 0xFF) as u8;
+        track_io!(writer.write_u8(byte4))?;
+
+        let byte5 = (((frame_len & 0b111) as u8) << 5) | 0b0001_1111; // buffer_fullness high bits, all set
+        track_io!(writer.write_u8(byte5))?;
+
+        track_io!(writer.write_u8(0xFC))?; // buffer_fullness low bits + number_of_raw_data_blocks=0 (encoded as 0b11111100)
+        Ok(())
+    }
+}
+
+/// Iterates over the Annex-B NAL units in an AVC/HEVC access unit,
+/// splitting on `0x000001`/`0x00000001` start codes.
+///
+/// Each yielded slice excludes the start code and runs up to (but not
+/// including) the next one, so it is ready to be length-prefixed for an
+/// MP4-style (AVCC/HVCC) sample.
+#[derive(Debug)]
+pub struct NalUnits<'a> {
+    data: &'a [u8],
+}
+impl<'a> NalUnits<'a> {
+    /// Makes a new `NalUnits` iterator over an Annex-B byte stream.
+    ///
+    /// If `data` does not begin with a start code (e.g. it was truncated
+    /// mid-access-unit), the leading bytes are treated as a single NAL
+    /// unit rather than silently discarded.
+    pub fn new(data: &'a [u8]) -> Self {
+        let start = find_start_code(data).map_or(0, |(pos, end)| if pos == 0 { end } else { 0 });
+        NalUnits { data: &data[start..] }
+    }
+}
+impl<'a> Iterator for NalUnits<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        match find_start_code(self.data) {
+            None => {
+                let nal = self.data;
+                self.data = &[];
+                Some(nal)
+            }
+            Some((start, end)) => {
+                let nal = &self.data[..start];
+                self.data = &self.data[end..];
+                Some(nal)
+            }
+        }
+    }
+}
+
+/// Finds the first `0x000001`/`0x00000001` start code in `data`, returning
+/// `(offset_of_first_zero, offset_just_past_the_0x01_byte)`.
+fn find_start_code(data: &[u8]) -> Option<(usize, usize)> {
+    let mut i = 0;
+    while i + 2 < data.len() {
+        if data[i] == 0 && data[i + 1] == 0 {
+            if data[i + 2] == 1 {
+                return Some((i, i + 3));
+            }
+            if data[i + 2] == 0 && i + 3 < data.len() && data[i + 3] == 1 {
+                return Some((i, i + 4));
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// How to reframe an elementary stream's access units for use outside of
+/// the TS/PES container, selected by `StreamType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EsFraming {
+    /// AAC access units, each to be prefixed with a 7-byte ADTS header
+    /// written by `AdtsHeaderParams::write_header`; the header's fields
+    /// come from `AdtsHeaderParams`, which `StreamType::Aac` alone does
+    /// not carry.
+    AdtsAac,
+
+    /// Annex-B byte-stream, e.g. for passthrough or for splitting into
+    /// NAL units via `NalUnits`.
+    AnnexB,
+}
+impl EsFraming {
+    /// Returns the framing to use for `stream_type`, or `None` if this
+    /// module has no special handling for it (the caller should fall back
+    /// to passing the PES payload through unmodified).
+    pub fn for_stream_type(stream_type: StreamType) -> Option<Self> {
+        match stream_type {
+            StreamType::Aac => Some(EsFraming::AdtsAac),
+            StreamType::H264 | StreamType::H265 => Some(EsFraming::AnnexB),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_adts_header(header: &[u8; AdtsHeaderParams::HEADER_LEN]) -> (u8, u8, u8, usize) {
+        let profile = header[2] >> 6;
+        let sampling_frequency_index = (header[2] >> 2) & 0b1111;
+        let channel_config = ((header[2] & 0b1) << 2) | (header[3] >> 6);
+        let frame_len = ((usize::from(header[3]) & 0b11) << 11)
+            | (usize::from(header[4]) << 3)
+            | (usize::from(header[5]) >> 5);
+        (profile, sampling_frequency_index, channel_config, frame_len)
+    }
+
+    #[test]
+    fn write_header_round_trips_field_values() {
+        let params = AdtsHeaderParams {
+            profile: 0b10,
+            sampling_frequency_index: 0b1011,
+            channel_config: 0b110,
+        };
+        let mut header = [0; AdtsHeaderParams::HEADER_LEN];
+        params.write_header(100, &mut header[..]).unwrap();
+
+        assert_eq!(header[0], 0xFF);
+        assert_eq!(header[1], 0xF1);
+        let (profile, sfi, channel_config, frame_len) = decode_adts_header(&header);
+        assert_eq!(profile, params.profile);
+        assert_eq!(sfi, params.sampling_frequency_index);
+        assert_eq!(channel_config, params.channel_config);
+        assert_eq!(frame_len, 100 + AdtsHeaderParams::HEADER_LEN);
+    }
+
+    #[test]
+    fn write_header_rejects_a_profile_that_does_not_fit_in_2_bits() {
+        let params = AdtsHeaderParams {
+            profile: 0b100,
+            sampling_frequency_index: 0,
+            channel_config: 0,
+        };
+        let mut header = Vec::new();
+        assert!(params.write_header(0, &mut header).is_err());
+    }
+
+    #[test]
+    fn write_header_rejects_a_frame_too_large_for_the_13_bit_length_field() {
+        let params = AdtsHeaderParams {
+            profile: 1,
+            sampling_frequency_index: 4,
+            channel_config: 2,
+        };
+        let mut header = Vec::new();
+        assert!(params.write_header(0x1FFF, &mut header).is_err());
+    }
+
+    #[test]
+    fn nal_units_splits_on_3_and_4_byte_start_codes() {
+        let data = [
+            &[0, 0, 1][..],
+            &[0xAA, 0xBB],
+            &[0, 0, 0, 1][..],
+            &[0xCC],
+        ].concat();
+        let nals: Vec<&[u8]> = NalUnits::new(&data).collect();
+        assert_eq!(nals, vec![&[0xAA, 0xBB][..], &[0xCC][..]]);
+    }
+
+    #[test]
+    fn nal_units_treats_data_without_a_leading_start_code_as_one_nal_unit() {
+        let data = [0xAA, 0xBB, 0xCC];
+        let nals: Vec<&[u8]> = NalUnits::new(&data).collect();
+        assert_eq!(nals, vec![&[0xAA, 0xBB, 0xCC][..]]);
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::Read;
+
+use {ErrorKind, ReaderOptions, Result};
+use pes::{PesHeader, PesPacket};
+use ts::reader::TsPacketReader;
+use ts::TsPayload;
+
+/// Reassembles PES packets (one or more TS payloads, starting with a
+/// packet whose `payload_unit_start_indicator` is set) read from an
+/// underlying `TsPacketReader`.
+#[derive(Debug)]
+pub struct PesPacketReader<R> {
+    inner: TsPacketReader<R>,
+    options: ReaderOptions,
+    // The TS payload that starts the *next* PES packet; buffered because
+    // it has to be read (to know the current PES packet ended) before the
+    // current packet can be returned.
+    pending: Option<Vec<u8>>,
+}
+
+/// A single TS payload, tagged with whether it started a new PES packet.
+struct Payload {
+    data: Vec<u8>,
+    payload_unit_start_indicator: bool,
+}
+impl<R: Read> PesPacketReader<R> {
+    /// Makes a new `PesPacketReader`, inheriting `inner`'s `ReaderOptions`.
+    pub fn new(inner: TsPacketReader<R>) -> Self {
+        let options = *inner.options();
+        Self::with_options(inner, options)
+    }
+
+    /// Like `new`, but enforces `options.max_pes_packet_len` instead of
+    /// whichever limit `inner` was constructed with, returning
+    /// `ErrorKind::InvalidInput` instead of growing the reassembly buffer
+    /// without bound when a PES packet (accumulated across many TS
+    /// payloads) exceeds it.
+    pub fn with_options(inner: TsPacketReader<R>, options: ReaderOptions) -> Self {
+        PesPacketReader {
+            inner,
+            options,
+            pending: None,
+        }
+    }
+
+    /// Reads the next PES packet, or `None` on a clean EOF between packets.
+    pub fn read_pes_packet(&mut self) -> Result<Option<PesPacket>> {
+        let mut data = match self.pending.take() {
+            Some(data) => data,
+            None => match track!(self.next_payload())? {
+                Some(payload) => payload.data,
+                None => return Ok(None),
+            },
+        };
+
+        loop {
+            match track!(self.next_payload())? {
+                None => break,
+                Some(payload) => {
+                    if payload.payload_unit_start_indicator {
+                        self.pending = Some(payload.data);
+                        break;
+                    }
+                    track!(Self::append(&mut data, &payload.data, self.options.max_pes_packet_len))?;
+                }
+            }
+        }
+
+        let mut reader = &data[..];
+        let header = track!(PesHeader::read_from(&mut reader))?;
+        let es_data = reader.to_owned();
+        Ok(Some(PesPacket {
+            header,
+            data: es_data,
+        }))
+    }
+
+    /// Reads TS packets (skipping any without a `Raw` payload) until one
+    /// with a payload is found, returning that payload tagged with whether
+    /// it starts a new PES packet.
+    fn next_payload(&mut self) -> Result<Option<Payload>> {
+        loop {
+            let packet = match track!(self.inner.read_ts_packet())? {
+                Some(packet) => packet,
+                None => return Ok(None),
+            };
+            if let Some(TsPayload::Raw(bytes)) = packet.payload {
+                return Ok(Some(Payload {
+                    data: bytes.as_bytes().to_owned(),
+                    payload_unit_start_indicator: packet.header.payload_unit_start_indicator,
+                }));
+            }
+        }
+    }
+
+    fn append(data: &mut Vec<u8>, next: &[u8], max_pes_packet_len: usize) -> Result<()> {
+        let new_len = data.len() + next.len();
+        track_assert!(
+            new_len <= max_pes_packet_len,
+            ErrorKind::InvalidInput,
+            "Reassembled PES packet exceeds the configured limit: {} bytes",
+            new_len
+        );
+        if data.try_reserve(next.len()).is_err() {
+            track_panic!(
+                ErrorKind::InvalidInput,
+                "Failed to allocate {} more bytes for a PES packet",
+                next.len()
+            );
+        }
+        data.extend_from_slice(next);
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use std::io::Write;
+use byteorder::{BigEndian, WriteBytesExt};
+
+use {ErrorKind, Result};
+use pes::PesHeader;
+use ts::payload::Bytes;
+use ts::writer::{TsPacketWriter, TS_PACKET_LEN};
+use ts::{Pid, TsHeader, TsPacket, TsPayload};
+
+/// The number of bytes available for a TS payload once the 4-byte TS
+/// header has been accounted for (assuming no adaptation field).
+const PAYLOAD_LEN: usize = TS_PACKET_LEN - 4;
+
+/// Splits PES packets across one or more TS packets and writes them out.
+///
+/// The first TS packet of a PES packet has `payload_unit_start_indicator`
+/// set, as required so that a reader knows where a new PES packet (and
+/// its header) begins; subsequent TS packets for the same access unit
+/// carry only the continuation of `PesPacket::data`.
+#[derive(Debug)]
+pub struct PesPacketWriter<W> {
+    inner: TsPacketWriter<W>,
+}
+impl<W: Write> PesPacketWriter<W> {
+    /// Makes a new `PesPacketWriter` instance.
+    pub fn new(inner: TsPacketWriter<W>) -> Self {
+        PesPacketWriter { inner }
+    }
+
+    /// Writes a PES packet, carrying `header` and `es_data` (a single
+    /// elementary stream access unit), to TS packets on `pid`.
+    pub fn write_pes_packet(&mut self, pid: Pid, header: &PesHeader, es_data: &[u8]) -> Result<()> {
+        let mut pes_packet = Vec::new();
+        track!(header.write_to(&mut pes_packet))?;
+
+        let pes_packet_len = pes_packet.len() + es_data.len() - 6;
+        track_assert!(
+            pes_packet_len <= 0xFFFF || header.is_unbounded(),
+            ErrorKind::InvalidInput,
+            "PES packet is too large to carry an explicit length: {} bytes",
+            pes_packet_len
+        );
+        {
+            let len_field = if header.is_unbounded() {
+                0
+            } else {
+                pes_packet_len as u16
+            };
+            let mut len_bytes = &mut pes_packet[4..6];
+            track_io!(len_bytes.write_u16::<BigEndian>(len_field))?;
+        }
+        pes_packet.extend_from_slice(es_data);
+
+        let mut payload_unit_start_indicator = true;
+        for chunk in pes_packet.chunks(PAYLOAD_LEN) {
+            let packet = TsPacket {
+                header: TsHeader {
+                    pid,
+                    payload_unit_start_indicator,
+                    ..TsHeader::default()
+                },
+                adaptation_field: None,
+                payload: Some(TsPayload::Raw(track!(Bytes::new(chunk))?)),
+            };
+            track!(self.inner.write_ts_packet(&packet))?;
+            payload_unit_start_indicator = false;
+        }
+        Ok(())
+    }
+
+    /// Consumes this writer, returning the underlying `TsPacketWriter`.
+    pub fn into_inner(self) -> TsPacketWriter<W> {
+        self.inner
+    }
+}
@@ -0,0 +1,29 @@
+/// Configurable limits applied while parsing (untrusted) MPEG-2 TS streams.
+///
+/// Without these caps, a malformed or hostile stream can drive unbounded
+/// allocation through repeated descriptor/ES-info loops, oversized PSI
+/// section lengths, or a PES payload that is never terminated. Readers
+/// that accept a `ReaderOptions` return `ErrorKind::InvalidInput` instead
+/// of growing a `Vec` without bound when a limit is exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReaderOptions {
+    /// Maximum size, in bytes, of a reassembled PES packet payload.
+    pub max_pes_packet_len: usize,
+
+    /// Maximum number of descriptors read from a single descriptor loop
+    /// (e.g. a PMT's program-level or per-ES descriptor list).
+    pub max_descriptor_loop_iterations: usize,
+
+    /// Maximum size, in bytes, of a PSI section (after concatenating all
+    /// of a multi-section table's `table_data`).
+    pub max_psi_section_len: usize,
+}
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        ReaderOptions {
+            max_pes_packet_len: 4 * 1024 * 1024,
+            max_descriptor_loop_iterations: 256,
+            max_psi_section_len: 4096,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::Read;
+use byteorder::ReadBytesExt;
+
+use Result;
+use ts::pmt::Descriptor;
+
+/// A typed decoding of a `Descriptor`'s `(tag, data)` pair.
+///
+/// Unknown tags (and any descriptor whose payload does not match the
+/// shape expected for a known tag) decode to `Raw`, so `Descriptor::decode`
+/// never fails.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DescriptorBody {
+    Registration(RegistrationDescriptor),
+    Iso639Language(Iso639LanguageDescriptor),
+    StreamIdentifier(StreamIdentifierDescriptor),
+    Ac3(Ac3Descriptor),
+    EnhancedAc3(EnhancedAc3Descriptor),
+    Raw { tag: u8, data: Vec<u8> },
+}
+impl DescriptorBody {
+    pub(crate) fn decode(d: &Descriptor) -> Self {
+        let fallback = || DescriptorBody::Raw {
+            tag: d.tag,
+            data: d.data.clone(),
+        };
+        match d.tag {
+            RegistrationDescriptor::TAG => RegistrationDescriptor::decode(&d.data)
+                .map(DescriptorBody::Registration)
+                .unwrap_or_else(|| fallback()),
+            Iso639LanguageDescriptor::TAG => Iso639LanguageDescriptor::decode(&d.data)
+                .map(DescriptorBody::Iso639Language)
+                .unwrap_or_else(|| fallback()),
+            StreamIdentifierDescriptor::TAG => StreamIdentifierDescriptor::decode(&d.data)
+                .map(DescriptorBody::StreamIdentifier)
+                .unwrap_or_else(|| fallback()),
+            Ac3Descriptor::TAG => DescriptorBody::Ac3(Ac3Descriptor {
+                data: d.data.clone(),
+            }),
+            EnhancedAc3Descriptor::TAG => DescriptorBody::EnhancedAc3(EnhancedAc3Descriptor {
+                data: d.data.clone(),
+            }),
+            _ => fallback(),
+        }
+    }
+}
+
+/// `registration_descriptor` (tag `0x05`).
+///
+/// Identifies the format of private data via a 4-byte registered FourCC
+/// (`format_identifier`), optionally followed by `additional_identification_info`.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegistrationDescriptor {
+    pub format_identifier: [u8; 4],
+    pub additional_identification_info: Vec<u8>,
+}
+impl RegistrationDescriptor {
+    const TAG: u8 = 0x05;
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 4 {
+            return None;
+        }
+        let mut format_identifier = [0; 4];
+        format_identifier.copy_from_slice(&data[..4]);
+        Some(RegistrationDescriptor {
+            format_identifier,
+            additional_identification_info: data[4..].to_owned(),
+        })
+    }
+}
+
+/// `ISO_639_language_descriptor` (tag `0x0A`).
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Iso639LanguageDescriptor {
+    pub languages: Vec<Iso639LanguageEntry>,
+}
+impl Iso639LanguageDescriptor {
+    const TAG: u8 = 0x0A;
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() % 4 != 0 {
+            return None;
+        }
+        let mut reader = data;
+        let mut languages = Vec::new();
+        while !reader.is_empty() {
+            let mut language_code = [0; 3];
+            reader.read_exact(&mut language_code).ok()?;
+            let audio_type = reader.read_u8().ok()?;
+            languages.push(Iso639LanguageEntry {
+                language_code,
+                audio_type,
+            });
+        }
+        Some(Iso639LanguageDescriptor { languages })
+    }
+}
+
+/// A single `ISO_639_language_descriptor` record: a 3-byte language code
+/// (e.g. `b"eng"`) plus an `audio_type` byte.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Iso639LanguageEntry {
+    pub language_code: [u8; 3],
+    pub audio_type: u8,
+}
+
+/// `stream_identifier_descriptor` (tag `0x52`).
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StreamIdentifierDescriptor {
+    pub component_tag: u8,
+}
+impl StreamIdentifierDescriptor {
+    const TAG: u8 = 0x52;
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() != 1 {
+            return None;
+        }
+        Some(StreamIdentifierDescriptor {
+            component_tag: data[0],
+        })
+    }
+}
+
+/// `AC-3_descriptor` (tag `0x6A`).
+///
+/// The internal layout is a series of optional flagged fields (ATSC A/52);
+/// callers that need individual fields can parse `data` further.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Ac3Descriptor {
+    pub data: Vec<u8>,
+}
+impl Ac3Descriptor {
+    const TAG: u8 = 0x6A;
+}
+
+/// `enhanced_AC-3_descriptor` (tag `0x7A`).
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnhancedAc3Descriptor {
+    pub data: Vec<u8>,
+}
+impl EnhancedAc3Descriptor {
+    const TAG: u8 = 0x7A;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(tag: u8, data: &[u8]) -> Descriptor {
+        Descriptor {
+            tag,
+            data: data.to_owned(),
+        }
+    }
+
+    #[test]
+    fn decodes_a_registration_descriptor() {
+        let d = descriptor(0x05, b"HEVCextra");
+        match d.decode() {
+            DescriptorBody::Registration(r) => {
+                assert_eq!(&r.format_identifier, b"HEVC");
+                assert_eq!(r.additional_identification_info, b"extra");
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decodes_an_iso_639_language_descriptor_with_multiple_entries() {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"eng");
+        data.push(0);
+        data.extend_from_slice(b"jpn");
+        data.push(1);
+        let d = descriptor(0x0A, &data);
+        match d.decode() {
+            DescriptorBody::Iso639Language(l) => {
+                assert_eq!(l.languages.len(), 2);
+                assert_eq!(&l.languages[0].language_code, b"eng");
+                assert_eq!(l.languages[0].audio_type, 0);
+                assert_eq!(&l.languages[1].language_code, b"jpn");
+                assert_eq!(l.languages[1].audio_type, 1);
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn falls_back_to_raw_for_a_malformed_known_tag() {
+        // ISO 639 entries are 4 bytes each; 3 bytes can't decode.
+        let d = descriptor(0x0A, b"eng");
+        match d.decode() {
+            DescriptorBody::Raw { tag, data } => {
+                assert_eq!(tag, 0x0A);
+                assert_eq!(data, b"eng");
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn falls_back_to_raw_for_an_unknown_tag() {
+        let d = descriptor(0xF0, b"?");
+        match d.decode() {
+            DescriptorBody::Raw { tag, data } => {
+                assert_eq!(tag, 0xF0);
+                assert_eq!(data, b"?");
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decodes_a_stream_identifier_descriptor() {
+        let d = descriptor(0x52, &[7]);
+        match d.decode() {
+            DescriptorBody::StreamIdentifier(s) => assert_eq!(s.component_tag, 7),
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+}
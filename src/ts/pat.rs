@@ -0,0 +1,129 @@
+use std::io::{Read, Write};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use {ErrorKind, ReaderOptions, Result};
+use ts::psi::Psi;
+use ts::psi_writer::PsiTableWriter;
+use ts::{Pid, VersionNumber};
+
+/// Program Association Table.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pat {
+    pub transport_stream_id: u16,
+    pub version_number: VersionNumber,
+    pub table: Vec<ProgramAssociation>,
+}
+impl Pat {
+    const TABLE_ID: u8 = 0;
+
+    pub(super) fn read_from<R: Read>(reader: R) -> Result<Self> {
+        Self::read_from_with_options(reader, &ReaderOptions::default())
+    }
+
+    /// Like `read_from`, but enforces the given limits instead of the
+    /// defaults, so a malformed or hostile PAT cannot drive unbounded
+    /// allocation.
+    pub(super) fn read_from_with_options<R: Read>(
+        reader: R,
+        opts: &ReaderOptions,
+    ) -> Result<Self> {
+        let mut psi = track!(Psi::read_from_with_options(reader, opts))?;
+        track_assert_eq!(psi.tables.len(), 1, ErrorKind::InvalidInput);
+
+        let table = psi.tables.pop().expect("Never fails");
+        let header = table.header;
+        track_assert_eq!(header.table_id, Self::TABLE_ID, ErrorKind::InvalidInput);
+        track_assert!(!header.private_bit, ErrorKind::InvalidInput);
+
+        let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
+        track_assert_eq!(syntax.section_number, 0, ErrorKind::InvalidInput);
+        track_assert_eq!(syntax.last_section_number, 0, ErrorKind::InvalidInput);
+        track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
+
+        let mut reader = &syntax.table_data[..];
+        let mut table = Vec::new();
+        while !reader.is_empty() {
+            table.push(track!(ProgramAssociation::read_from(&mut reader))?);
+        }
+        Ok(Pat {
+            transport_stream_id: syntax.table_id_extension,
+            version_number: syntax.version_number,
+            table,
+        })
+    }
+
+    /// Serializes this PAT as a single PSI section.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        let mut table_data = Vec::new();
+        for association in &self.table {
+            track!(association.write_to(&mut table_data))?;
+        }
+        track!(PsiTableWriter::write_section(
+            writer,
+            Self::TABLE_ID,
+            false,
+            self.transport_stream_id,
+            self.version_number,
+            true,
+            0,
+            0,
+            &table_data,
+        ))?;
+        Ok(())
+    }
+}
+
+/// An entry of `Pat::table`, associating a program number with the PID of
+/// its PMT (or, for `program_num == 0`, the network information table).
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProgramAssociation {
+    pub program_num: u16,
+    pub program_map_pid: Pid,
+}
+impl ProgramAssociation {
+    fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let program_num = track_io!(reader.read_u16::<BigEndian>())?;
+        let program_map_pid = track!(Pid::read_from(&mut reader))?;
+        Ok(ProgramAssociation {
+            program_num,
+            program_map_pid,
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u16::<BigEndian>(self.program_num))?;
+        track!(self.program_map_pid.write_to(&mut writer))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_to_round_trips_through_read_from() {
+        let pat = Pat {
+            transport_stream_id: 1,
+            version_number: VersionNumber::new(3),
+            table: vec![
+                ProgramAssociation {
+                    program_num: 0,
+                    program_map_pid: Pid::new(0x10).unwrap(),
+                },
+                ProgramAssociation {
+                    program_num: 1,
+                    program_map_pid: Pid::new(0x100).unwrap(),
+                },
+            ],
+        };
+
+        let mut payload = vec![0]; // pointer_field, as prepended by the TS/PES layer
+        pat.write_to(&mut payload).unwrap();
+
+        let decoded = Pat::read_from(&payload[..]).unwrap();
+        assert_eq!(decoded, pat);
+    }
+}
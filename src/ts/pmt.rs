@@ -1,10 +1,12 @@
-use std::io::Read;
-use byteorder::{BigEndian, ReadBytesExt};
+use std::io::{Read, Write};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
-use {ErrorKind, Result};
+use {ErrorKind, ReaderOptions, Result};
 use es::StreamType;
 use ts::{Pid, VersionNumber};
+use ts::descriptor::DescriptorBody;
 use ts::psi::Psi;
+use ts::psi_writer::PsiTableWriter;
 
 /// Program Map Table.
 #[allow(missing_docs)]
@@ -19,26 +21,88 @@ pub struct Pmt {
     pub pcr_pid: Option<Pid>,
 
     pub version_number: VersionNumber,
+
+    /// Program-level descriptors (e.g. CA or registration descriptors).
+    pub program_descriptors: Vec<Descriptor>,
+
     pub table: Vec<EsInfo>,
 }
 impl Pmt {
     const TABLE_ID: u8 = 2;
 
     pub(super) fn read_from<R: Read>(reader: R) -> Result<Self> {
-        let mut psi = track!(Psi::read_from(reader))?;
-        track_assert_eq!(psi.tables.len(), 1, ErrorKind::InvalidInput);
+        Self::read_from_with_options(reader, &ReaderOptions::default())
+    }
 
-        let table = psi.tables.pop().expect("Never fails");
-        let header = table.header;
-        track_assert_eq!(header.table_id, Self::TABLE_ID, ErrorKind::InvalidInput);
-        track_assert!(!header.private_bit, ErrorKind::InvalidInput);
+    /// Like `read_from`, but enforces the given limits instead of the
+    /// defaults, so a malformed or hostile PMT cannot drive unbounded
+    /// allocation.
+    pub(super) fn read_from_with_options<R: Read>(
+        reader: R,
+        opts: &ReaderOptions,
+    ) -> Result<Self> {
+        // `Psi::read_from_with_options` caps each individual on-wire
+        // section; since a multi-section PMT's `table_data` is
+        // concatenated across up to 256 sections below, that per-section
+        // cap alone doesn't bound the reassembled total, so it is
+        // re-checked explicitly once `total_len` is known.
+        let mut psi = track!(Psi::read_from_with_options(reader, opts))?;
+        track_assert!(!psi.tables.is_empty(), ErrorKind::InvalidInput);
 
-        let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
-        track_assert_eq!(syntax.section_number, 0, ErrorKind::InvalidInput);
-        track_assert_eq!(syntax.last_section_number, 0, ErrorKind::InvalidInput);
-        track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
+        psi.tables
+            .sort_by_key(|t| t.syntax.as_ref().map(|s| s.section_number));
 
-        let mut reader = &syntax.table_data[..];
+        let last_section_number = {
+            let syntax = track_assert_some!(
+                psi.tables[0].syntax.as_ref(),
+                ErrorKind::InvalidInput
+            );
+            syntax.last_section_number
+        };
+        track_assert_eq!(
+            psi.tables.len(),
+            usize::from(last_section_number) + 1,
+            ErrorKind::InvalidInput,
+            "Missing PMT section(s)"
+        );
+
+        let total_len: usize = psi.tables.iter().map(|t| t.syntax.as_ref().map_or(0, |s| s.table_data.len())).sum();
+        track_assert!(
+            total_len <= opts.max_psi_section_len,
+            ErrorKind::InvalidInput,
+            "Reassembled PMT exceeds the configured limit: {} bytes",
+            total_len
+        );
+        let mut table_data = Vec::new();
+        if table_data.try_reserve(total_len).is_err() {
+            track_panic!(
+                ErrorKind::InvalidInput,
+                "Failed to allocate {} bytes for PMT table data",
+                total_len
+            );
+        }
+        let mut program_num = None;
+        let mut version_number = None;
+        for (i, table) in psi.tables.iter().enumerate() {
+            let header = &table.header;
+            track_assert_eq!(header.table_id, Self::TABLE_ID, ErrorKind::InvalidInput);
+            track_assert!(!header.private_bit, ErrorKind::InvalidInput);
+
+            let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
+            track_assert_eq!(syntax.section_number, i as u8, ErrorKind::InvalidInput);
+            track_assert_eq!(
+                syntax.last_section_number,
+                last_section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
+
+            program_num.get_or_insert(syntax.table_id_extension);
+            version_number.get_or_insert(syntax.version_number);
+            table_data.extend_from_slice(&syntax.table_data);
+        }
+
+        let mut reader = &table_data[..];
 
         let pcr_pid = track!(Pid::read_from(&mut reader))?;
         let pcr_pid = if pcr_pid.as_u16() == 0b0001_1111_1111_1111 {
@@ -61,19 +125,81 @@ impl Pmt {
             "Unexpected program info length unused bits"
         );
         let program_info_len = n & 0b0000_0011_1111_1111;
-        track_assert_eq!(program_info_len, 0, ErrorKind::Unsupported);
+
+        let mut program_info_reader = reader.take(u64::from(program_info_len));
+        let mut program_descriptors = Vec::new();
+        while program_info_reader.limit() > 0 {
+            track_assert!(
+                program_descriptors.len() < opts.max_descriptor_loop_iterations,
+                ErrorKind::InvalidInput,
+                "Too many program descriptors"
+            );
+            let d = track!(Descriptor::read_from(&mut program_info_reader))?;
+            program_descriptors.push(d);
+        }
+        track_assert_eq!(program_info_reader.limit(), 0, ErrorKind::InvalidInput);
+        reader = program_info_reader.into_inner();
 
         let mut table = Vec::new();
         while !reader.is_empty() {
-            table.push(track!(EsInfo::read_from(&mut reader))?);
+            track_assert!(
+                table.len() < opts.max_descriptor_loop_iterations,
+                ErrorKind::InvalidInput,
+                "Too many elementary streams"
+            );
+            table.push(track!(EsInfo::read_from_with_options(&mut reader, opts))?);
         }
         Ok(Pmt {
-            program_num: syntax.table_id_extension,
+            program_num: program_num.expect("Never fails"),
             pcr_pid,
-            version_number: syntax.version_number,
+            version_number: version_number.expect("Never fails"),
+            program_descriptors,
             table,
         })
     }
+
+    /// Serializes this PMT as a single PSI section.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        let mut table_data = Vec::new();
+
+        let pcr_pid = self.pcr_pid
+            .unwrap_or_else(|| Pid::new(0b0001_1111_1111_1111).expect("Never fails"));
+        track!(pcr_pid.write_to(&mut table_data))?;
+
+        let mut program_info = Vec::new();
+        for d in &self.program_descriptors {
+            track!(d.write_to(&mut program_info))?;
+        }
+        let program_info_len = program_info.len() as u16;
+        track_assert!(
+            program_info_len & 0b1111_1100_0000_0000 == 0,
+            ErrorKind::InvalidInput,
+            "Too many/large program descriptors: {} bytes",
+            program_info_len
+        );
+        track_io!(table_data.write_u16::<BigEndian>(0b1111_0000_0000_0000 | program_info_len))?;
+        table_data.extend_from_slice(&program_info);
+
+        // This writer always emits a single-section PMT; splitting a large
+        // table across sections mirrors `Self::read_from`'s reassembly but
+        // is not needed by any current caller.
+        for es_info in &self.table {
+            track!(es_info.write_to(&mut table_data))?;
+        }
+
+        track!(PsiTableWriter::write_section(
+            writer,
+            Self::TABLE_ID,
+            false,
+            self.program_num,
+            self.version_number,
+            true,
+            0,
+            0,
+            &table_data,
+        ))?;
+        Ok(())
+    }
 }
 
 /// Elementary stream information.
@@ -88,7 +214,7 @@ pub struct EsInfo {
     pub descriptors: Vec<Descriptor>,
 }
 impl EsInfo {
-    fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+    fn read_from_with_options<R: Read>(mut reader: R, opts: &ReaderOptions) -> Result<Self> {
         let stream_type = track_io!(reader.read_u8()).and_then(StreamType::from_u8)?;
         let elementary_pid = track!(Pid::read_from(&mut reader))?;
 
@@ -110,6 +236,11 @@ impl EsInfo {
         let mut reader = reader.take(u64::from(es_info_len));
         let mut descriptors = Vec::new();
         while reader.limit() > 0 {
+            track_assert!(
+                descriptors.len() < opts.max_descriptor_loop_iterations,
+                ErrorKind::InvalidInput,
+                "Too many ES descriptors"
+            );
             let d = track!(Descriptor::read_from(&mut reader))?;
             descriptors.push(d);
         }
@@ -121,6 +252,26 @@ impl EsInfo {
             descriptors,
         })
     }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u8(self.stream_type.as_u8()))?;
+        track!(self.elementary_pid.write_to(&mut writer))?;
+
+        let mut descriptors = Vec::new();
+        for d in &self.descriptors {
+            track!(d.write_to(&mut descriptors))?;
+        }
+        let es_info_len = descriptors.len() as u16;
+        track_assert!(
+            es_info_len & 0b1111_1100_0000_0000 == 0,
+            ErrorKind::InvalidInput,
+            "Too many/large descriptors: {} bytes",
+            es_info_len
+        );
+        track_io!(writer.write_u16::<BigEndian>(0b1111_0000_0000_0000 | es_info_len))?;
+        track_io!(writer.write_all(&descriptors))?;
+        Ok(())
+    }
 }
 
 /// Program or elementary stream descriptor.
@@ -138,4 +289,150 @@ impl Descriptor {
         track_io!(reader.read_exact(&mut data))?;
         Ok(Descriptor { tag, data })
     }
+
+    /// Decodes this descriptor's `(tag, data)` pair into a typed representation.
+    ///
+    /// Unknown tags (and malformed payloads for known tags) decode to
+    /// `DescriptorBody::Raw`, so this never fails.
+    pub fn decode(&self) -> DescriptorBody {
+        DescriptorBody::decode(self)
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(
+            self.data.len() <= 0xFF,
+            ErrorKind::InvalidInput,
+            "Descriptor data is too large: {} bytes",
+            self.data.len()
+        );
+        track_io!(writer.write_u8(self.tag))?;
+        track_io!(writer.write_u8(self.data.len() as u8))?;
+        track_io!(writer.write_all(&self.data))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_pmt() -> Pmt {
+        Pmt {
+            program_num: 1,
+            pcr_pid: Some(Pid::new(0x101).unwrap()),
+            version_number: VersionNumber::new(1),
+            program_descriptors: vec![Descriptor {
+                tag: 0x05,
+                data: b"HEVC".to_vec(),
+            }],
+            table: vec![
+                EsInfo {
+                    stream_type: StreamType::H264,
+                    elementary_pid: Pid::new(0x101).unwrap(),
+                    descriptors: vec![],
+                },
+                EsInfo {
+                    stream_type: StreamType::Aac,
+                    elementary_pid: Pid::new(0x102).unwrap(),
+                    descriptors: vec![Descriptor {
+                        tag: 0x0A,
+                        data: b"eng\0".to_vec(),
+                    }],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn write_to_round_trips_through_read_from() {
+        let pmt = sample_pmt();
+        let mut payload = vec![0]; // pointer_field, as prepended by the TS/PES layer
+        pmt.write_to(&mut payload).unwrap();
+
+        let decoded = Pmt::read_from(&payload[..]).unwrap();
+        assert_eq!(decoded, pmt);
+    }
+
+    #[test]
+    fn read_from_reassembles_a_pmt_split_across_multiple_sections() {
+        let pmt = sample_pmt();
+
+        // Mirror `write_to`'s table_data layout, but split it into two
+        // sections at an arbitrary boundary to exercise reassembly.
+        let mut table_data = Vec::new();
+        pmt.pcr_pid
+            .unwrap_or_else(|| Pid::new(0b0001_1111_1111_1111).unwrap())
+            .write_to(&mut table_data)
+            .unwrap();
+        let mut program_info = Vec::new();
+        for d in &pmt.program_descriptors {
+            d.write_to(&mut program_info).unwrap();
+        }
+        table_data
+            .write_u16::<BigEndian>(0b1111_0000_0000_0000 | program_info.len() as u16)
+            .unwrap();
+        table_data.extend_from_slice(&program_info);
+        for es_info in &pmt.table {
+            es_info.write_to(&mut table_data).unwrap();
+        }
+
+        let split = table_data.len() / 2;
+        let (first_half, second_half) = table_data.split_at(split);
+
+        let mut payload = vec![0]; // pointer_field
+        PsiTableWriter::write_section(
+            &mut payload,
+            Pmt::TABLE_ID,
+            false,
+            pmt.program_num,
+            pmt.version_number,
+            true,
+            0,
+            1,
+            first_half,
+        ).unwrap();
+        PsiTableWriter::write_section(
+            &mut payload,
+            Pmt::TABLE_ID,
+            false,
+            pmt.program_num,
+            pmt.version_number,
+            true,
+            1,
+            1,
+            second_half,
+        ).unwrap();
+
+        let decoded = Pmt::read_from(&payload[..]).unwrap();
+        assert_eq!(decoded, pmt);
+    }
+
+    #[test]
+    fn read_from_with_options_rejects_a_multi_section_pmt_whose_reassembled_size_exceeds_the_limit(
+    ) {
+        // Each individual section stays within the limit, but the two
+        // together, once concatenated, exceed it.
+        let table_data = vec![0xAA; 200];
+        let opts = ReaderOptions {
+            max_psi_section_len: 300,
+            ..ReaderOptions::default()
+        };
+
+        let mut payload = vec![0]; // pointer_field
+        for section_number in 0..2 {
+            PsiTableWriter::write_section(
+                &mut payload,
+                Pmt::TABLE_ID,
+                false,
+                1,
+                VersionNumber::new(0),
+                true,
+                section_number,
+                1,
+                &table_data,
+            ).unwrap();
+        }
+
+        assert!(Pmt::read_from_with_options(&payload[..], &opts).is_err());
+    }
 }
\ No newline at end of file
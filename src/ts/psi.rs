@@ -0,0 +1,200 @@
+use std::io::Read;
+use byteorder::{BigEndian, ReadBytesExt};
+
+use {ErrorKind, ReaderOptions, Result};
+use ts::VersionNumber;
+
+/// A parsed PSI (Program Specific Information) section, as carried by
+/// PAT/PMT payloads (after TS/PES-level reassembly for the section's PID
+/// has already produced a contiguous byte stream).
+#[derive(Debug, Clone)]
+pub struct PsiTable {
+    pub header: PsiTableHeader,
+    pub syntax: Option<PsiTableSyntax>,
+}
+
+/// The fixed, always-present part of a PSI section.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy)]
+pub struct PsiTableHeader {
+    pub table_id: u8,
+    pub private_bit: bool,
+}
+
+/// The `section_syntax_indicator == 1` part of a PSI section (present for
+/// every table type this crate parses: PAT, PMT).
+#[allow(missing_docs)]
+#[derive(Debug, Clone)]
+pub struct PsiTableSyntax {
+    pub table_id_extension: u16,
+    pub version_number: VersionNumber,
+    pub current_next_indicator: bool,
+    pub section_number: u8,
+    pub last_section_number: u8,
+    pub table_data: Vec<u8>,
+}
+
+/// One or more PSI sections read from a single reassembled payload.
+#[derive(Debug, Clone)]
+pub struct Psi {
+    pub tables: Vec<PsiTable>,
+}
+impl Psi {
+    /// Reads PSI sections (terminated by a `table_id == 0xFF` stuffing
+    /// byte or EOF), applying the default `ReaderOptions`.
+    pub(crate) fn read_from<R: Read>(reader: R) -> Result<Self> {
+        Self::read_from_with_options(reader, &ReaderOptions::default())
+    }
+
+    /// Like `read_from`, but caps each section's length at
+    /// `opts.max_psi_section_len`, returning `ErrorKind::InvalidInput`
+    /// instead of reading (and allocating for) an unbounded section.
+    pub(crate) fn read_from_with_options<R: Read>(mut reader: R, opts: &ReaderOptions) -> Result<Self> {
+        let pointer_field = track_io!(reader.read_u8())?;
+        let mut pointer_data = vec![0; pointer_field as usize];
+        track_io!(reader.read_exact(&mut pointer_data))?;
+
+        let mut tables = Vec::new();
+        loop {
+            let mut table_id_byte = [0; 1];
+            let n = track_io!(reader.read(&mut table_id_byte))?;
+            if n == 0 {
+                break; // Clean EOF between sections.
+            }
+            let table_id = table_id_byte[0];
+            if table_id == 0xFF {
+                break; // stuffing
+            }
+
+            let n = track_io!(reader.read_u16::<BigEndian>())?;
+            let section_syntax_indicator = n & 0b1000_0000_0000_0000 != 0;
+            let private_bit = n & 0b0100_0000_0000_0000 != 0;
+            let section_length = n & 0b0000_1111_1111_1111;
+            track_assert!(
+                (section_length as usize) <= opts.max_psi_section_len,
+                ErrorKind::InvalidInput,
+                "PSI section is too large: {} bytes",
+                section_length
+            );
+
+            let header = PsiTableHeader {
+                table_id,
+                private_bit,
+            };
+            let syntax = if section_syntax_indicator {
+                Some(track!(Self::read_syntax(&mut reader, section_length))?)
+            } else {
+                let mut table_data = vec![0; section_length as usize];
+                track_io!(reader.read_exact(&mut table_data))?;
+                None
+            };
+            tables.push(PsiTable { header, syntax });
+        }
+        Ok(Psi { tables })
+    }
+
+    fn read_syntax<R: Read>(mut reader: R, section_length: u16) -> Result<PsiTableSyntax> {
+        // section_length covers: table_id_extension(2) + version/cni(1) +
+        // section_number(1) + last_section_number(1) + table_data + crc32(4).
+        track_assert!(
+            section_length as usize >= 5 + 4,
+            ErrorKind::InvalidInput,
+            "PSI section is too short to carry its syntax fields"
+        );
+
+        let table_id_extension = track_io!(reader.read_u16::<BigEndian>())?;
+        let b = track_io!(reader.read_u8())?;
+        let version_number = VersionNumber::new((b >> 1) & 0b0001_1111);
+        let current_next_indicator = b & 0b0000_0001 != 0;
+        let section_number = track_io!(reader.read_u8())?;
+        let last_section_number = track_io!(reader.read_u8())?;
+
+        let table_data_len = section_length as usize - 5 - 4;
+        let mut table_data = Vec::new();
+        if table_data.try_reserve(table_data_len).is_err() {
+            track_panic!(
+                ErrorKind::InvalidInput,
+                "Failed to allocate {} bytes for a PSI section",
+                table_data_len
+            );
+        }
+        table_data.resize(table_data_len, 0);
+        track_io!(reader.read_exact(&mut table_data))?;
+
+        let _crc32 = track_io!(reader.read_u32::<BigEndian>())?;
+
+        Ok(PsiTableSyntax {
+            table_id_extension,
+            version_number,
+            current_next_indicator,
+            section_number,
+            last_section_number,
+            table_data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ts::psi_writer::PsiTableWriter;
+
+    #[test]
+    fn reads_back_a_section_written_by_psi_table_writer() {
+        let table_data = vec![0xAA; 10];
+        let mut bytes = Vec::new();
+        PsiTableWriter::write_section(
+            &mut bytes,
+            2,
+            false,
+            0x1234,
+            VersionNumber::new(7),
+            true,
+            0,
+            0,
+            &table_data,
+        ).unwrap();
+
+        let mut payload = vec![0]; // pointer_field
+        payload.extend_from_slice(&bytes);
+
+        let psi = Psi::read_from(&payload[..]).unwrap();
+        assert_eq!(psi.tables.len(), 1);
+
+        let table = &psi.tables[0];
+        assert_eq!(table.header.table_id, 2);
+        assert!(!table.header.private_bit);
+
+        let syntax = table.syntax.as_ref().unwrap();
+        assert_eq!(syntax.table_id_extension, 0x1234);
+        assert_eq!(syntax.version_number.as_u8(), 7);
+        assert!(syntax.current_next_indicator);
+        assert_eq!(syntax.table_data, table_data);
+    }
+
+    #[test]
+    fn rejects_a_section_longer_than_the_configured_limit() {
+        let table_data = vec![0xAA; 10];
+        let mut bytes = Vec::new();
+        PsiTableWriter::write_section(
+            &mut bytes,
+            2,
+            false,
+            0x1234,
+            VersionNumber::new(0),
+            true,
+            0,
+            0,
+            &table_data,
+        ).unwrap();
+
+        let mut payload = vec![0];
+        payload.extend_from_slice(&bytes);
+
+        let opts = ReaderOptions {
+            max_psi_section_len: 4,
+            ..ReaderOptions::default()
+        };
+        assert!(Psi::read_from_with_options(&payload[..], &opts).is_err());
+    }
+}
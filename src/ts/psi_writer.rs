@@ -0,0 +1,132 @@
+use std::io::Write;
+use byteorder::{BigEndian, WriteBytesExt};
+
+use Result;
+use ts::VersionNumber;
+
+/// Computes the CRC-32/MPEG checksum (poly `0x04C11DB7`, init `0xFFFFFFFF`,
+/// not reflected, no final XOR) used to terminate PSI sections.
+///
+/// This is distinct from CRC-32/IEEE (used by zlib/PNG/etc.), which
+/// reflects its input/output and XORs the result with `0xFFFFFFFF`.
+fn crc32_mpeg(data: &[u8]) -> u32 {
+    const POLY: u32 = 0x04C1_1DB7;
+    let mut crc = 0xFFFF_FFFF_u32;
+    for &byte in data {
+        crc ^= u32::from(byte) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ POLY
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Serializes a single PSI section (as used by `Pat` and `Pmt`) to its
+/// on-the-wire representation, recomputing `section_length` and the
+/// trailing CRC-32/MPEG.
+///
+/// This mirrors the section layout consumed by `Psi::read_from`: a
+/// one-byte `table_id`, the `section_syntax_indicator`/`private_bit`/
+/// `section_length` word, the table-id-extension/version/section-number
+/// fields, the caller-supplied `table_data`, and a four-byte CRC.
+#[derive(Debug)]
+pub struct PsiTableWriter;
+impl PsiTableWriter {
+    /// Writes one PSI section.
+    ///
+    /// `table_data` is the already-encoded, table-specific payload (e.g.
+    /// the program associations for a PAT, or the program info and
+    /// elementary stream descriptors for a PMT).
+    pub fn write_section<W: Write>(
+        mut writer: W,
+        table_id: u8,
+        private_bit: bool,
+        table_id_extension: u16,
+        version_number: VersionNumber,
+        current_next_indicator: bool,
+        section_number: u8,
+        last_section_number: u8,
+        table_data: &[u8],
+    ) -> Result<()> {
+        let mut section = Vec::new();
+        track_io!(section.write_u16::<BigEndian>(table_id_extension))?;
+        let version_byte = 0b1100_0000
+            | (version_number.as_u8() << 1)
+            | if current_next_indicator { 1 } else { 0 };
+        track_io!(section.write_u8(version_byte))?;
+        track_io!(section.write_u8(section_number))?;
+        track_io!(section.write_u8(last_section_number))?;
+        section.extend_from_slice(table_data);
+
+        // section_length covers everything after the length field up to
+        // and including the trailing CRC-32.
+        let section_length = section.len() as u16 + 4;
+        track_assert!(
+            section_length & 0b1111_0000_0000_0000 == 0,
+            ::ErrorKind::InvalidInput,
+            "Section is too large: {} bytes",
+            section_length
+        );
+
+        let mut body = Vec::new();
+        track_io!(body.write_u8(table_id))?;
+        let indicator_word = 0b1000_0000
+            | if private_bit { 0b0100_0000 } else { 0 }
+            | 0b0011_0000
+            | ((section_length >> 8) & 0b0000_1111_1111_1111) as u8;
+        track_io!(body.write_u8(indicator_word))?;
+        track_io!(body.write_u8((section_length & 0b1111_1111) as u8))?;
+        body.extend_from_slice(&section);
+
+        let crc = crc32_mpeg(&body);
+
+        track_io!(writer.write_all(&body))?;
+        track_io!(writer.write_u32::<BigEndian>(crc))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_mpeg_matches_the_standard_check_value() {
+        // The official CRC-32/MPEG-2 check value, for ASCII input "123456789".
+        assert_eq!(crc32_mpeg(b"123456789"), 0x0376_E6E7);
+    }
+
+    #[test]
+    fn write_section_round_trips_through_section_length_and_crc() {
+        let table_data = vec![1, 2, 3, 4, 5];
+        let mut out = Vec::new();
+        PsiTableWriter::write_section(
+            &mut out,
+            2,
+            false,
+            0x1234,
+            VersionNumber::new(3),
+            true,
+            0,
+            0,
+            &table_data,
+        ).unwrap();
+
+        // table_id, indicator word, section_length, syntax fields, table_data, 4-byte CRC.
+        assert_eq!(out.len(), 1 + 2 + 5 + table_data.len() + 4);
+
+        let section_length = (u16::from(out[1] & 0b0000_1111) << 8) | u16::from(out[2]);
+        assert_eq!(section_length as usize, out.len() - 3);
+
+        let crc = crc32_mpeg(&out[..out.len() - 4]);
+        let expected_crc = (u32::from(out[out.len() - 4]) << 24)
+            | (u32::from(out[out.len() - 3]) << 16)
+            | (u32::from(out[out.len() - 2]) << 8)
+            | u32::from(out[out.len() - 1]);
+        assert_eq!(crc, expected_crc);
+    }
+}
@@ -0,0 +1,92 @@
+use std::io::Read;
+use byteorder::ReadBytesExt;
+
+use {ErrorKind, ReaderOptions, Result};
+use ts::payload::Bytes;
+use ts::writer::TS_PACKET_LEN;
+use ts::{AdaptationField, Pid, TsHeader, TsPacket, TsPayload};
+
+/// Reads `TsPacket`s from an underlying byte stream.
+#[derive(Debug)]
+pub struct TsPacketReader<R> {
+    inner: R,
+    options: ReaderOptions,
+}
+impl<R: Read> TsPacketReader<R> {
+    /// Makes a new `TsPacketReader` instance, using the default `ReaderOptions`.
+    pub fn new(inner: R) -> Self {
+        Self::with_options(inner, ReaderOptions::default())
+    }
+
+    /// Like `new`, but parses with the given limits instead of the
+    /// defaults. The options are also handed to any `PesPacketReader`
+    /// built on top of this reader via `PesPacketReader::new`.
+    pub fn with_options(inner: R, options: ReaderOptions) -> Self {
+        TsPacketReader { inner, options }
+    }
+
+    /// Returns the `ReaderOptions` this reader was constructed with.
+    pub fn options(&self) -> &ReaderOptions {
+        &self.options
+    }
+
+    /// Reads the next TS packet, or `None` on a clean EOF between packets.
+    pub fn read_ts_packet(&mut self) -> Result<Option<TsPacket>> {
+        let mut buf = [0; TS_PACKET_LEN];
+        let mut read = 0;
+        while read < buf.len() {
+            let n = track_io!(self.inner.read(&mut buf[read..]))?;
+            if n == 0 {
+                break;
+            }
+            read += n;
+        }
+        if read == 0 {
+            return Ok(None);
+        }
+        track_assert_eq!(read, buf.len(), ErrorKind::InvalidInput, "Truncated TS packet");
+        track_assert_eq!(buf[0], 0x47, ErrorKind::InvalidInput, "Missing sync byte");
+
+        let mut reader = &buf[1..];
+        let byte1 = track_io!(reader.read_u8())?;
+        let byte2 = track_io!(reader.read_u8())?;
+        let transport_error_indicator = byte1 & 0b1000_0000 != 0;
+        let payload_unit_start_indicator = byte1 & 0b0100_0000 != 0;
+        let transport_priority = byte1 & 0b0010_0000 != 0;
+        let pid = track_assert_some!(
+            Pid::new((u16::from(byte1 & 0b0001_1111) << 8) | u16::from(byte2)),
+            ErrorKind::InvalidInput
+        );
+
+        let byte4 = track_io!(reader.read_u8())?;
+        let transport_scrambling_control = track!(::ts::TransportScramblingControl::from_u8(
+            byte4 >> 6
+        ))?;
+        let has_adaptation_field = byte4 & 0b0010_0000 != 0;
+        let has_payload = byte4 & 0b0001_0000 != 0;
+
+        let adaptation_field = if has_adaptation_field {
+            Some(track!(AdaptationField::read_from(&mut reader))?)
+        } else {
+            None
+        };
+        let payload = if has_payload {
+            Some(TsPayload::Raw(track!(Bytes::new(reader))?))
+        } else {
+            None
+        };
+
+        Ok(Some(TsPacket {
+            header: TsHeader {
+                transport_error_indicator,
+                payload_unit_start_indicator,
+                transport_priority,
+                pid,
+                transport_scrambling_control,
+                ..TsHeader::default()
+            },
+            adaptation_field,
+            payload,
+        }))
+    }
+}
@@ -0,0 +1,251 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use {ErrorKind, Result};
+use ts::Pid;
+use ts::writer::TS_PACKET_LEN;
+
+/// A 27 MHz program clock reference, as carried by a TS packet's
+/// adaptation field: `base * 300 + extension`, where `base` is a 33-bit
+/// 90 kHz counter and `extension` is a 9-bit 27 MHz counter.
+pub type Pcr = u64;
+
+const PCR_HZ: u64 = 27_000_000;
+
+/// Converts a PCR value to seconds, counting from a PCR of zero.
+pub fn pcr_to_secs(pcr: Pcr) -> f64 {
+    pcr as f64 / PCR_HZ as f64
+}
+
+/// An entry in a `TsSeeker`'s index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IndexEntry {
+    pcr: Pcr,
+    byte_offset: u64,
+}
+
+/// Builds and queries a PCR-to-byte-offset index over a seekable TS
+/// stream, to support random access without reading from the start.
+///
+/// PCR is a 33-bit, 90 kHz counter that wraps around roughly every 26.5
+/// hours; a `discontinuity_indicator` in the adaptation field (or the
+/// counter itself decreasing) starts a new index segment so that seeking
+/// never binary-searches across a discontinuity.
+#[derive(Debug)]
+pub struct TsSeeker<R> {
+    inner: R,
+    pcr_pid: Pid,
+    // Segments are stored in order of construction; PCRs are monotonic
+    // (ignoring wraparound) within a segment and reset across segments.
+    segments: Vec<Vec<IndexEntry>>,
+}
+impl<R: Read + Seek> TsSeeker<R> {
+    /// Scans `inner` from its current position to EOF, building a PCR
+    /// index over the packets on `pcr_pid` (typically `Pmt::pcr_pid`).
+    ///
+    /// `inner`'s current position need not already be on a packet
+    /// boundary: this resyncs to the first sync byte (`0x47`) it finds,
+    /// then walks multiples of 188 bytes from there.
+    pub fn new(mut inner: R, pcr_pid: Pid) -> Result<Self> {
+        let start = track_io!(inner.seek(SeekFrom::Current(0)))?;
+        let start = track!(find_first_sync_byte(&mut inner, start))?;
+        track_io!(inner.seek(SeekFrom::Start(start)))?;
+
+        let mut segments = vec![Vec::new()];
+        let mut last_pcr = None;
+        let mut buf = [0; TS_PACKET_LEN];
+        let mut offset = start;
+        loop {
+            let n = track_io!(read_all_or_eof(&mut inner, &mut buf))?;
+            if n == 0 {
+                break;
+            }
+            track_assert_eq!(n, TS_PACKET_LEN, ErrorKind::InvalidInput, "Truncated TS packet");
+            track_assert_eq!(buf[0], 0x47, ErrorKind::InvalidInput, "Missing sync byte");
+
+            let pid = Pid::new(u16::from(buf[1] & 0b0001_1111) << 8 | u16::from(buf[2]))
+                .expect("Masked to 13 bits");
+            if pid == pcr_pid {
+                if let Some((pcr, discontinuity)) = track!(read_pcr(&buf))? {
+                    let starts_new_segment = discontinuity
+                        || last_pcr.map_or(false, |p: Pcr| pcr < p);
+                    if starts_new_segment && !segments.last().expect("Never empty").is_empty() {
+                        segments.push(Vec::new());
+                    }
+                    segments
+                        .last_mut()
+                        .expect("Never empty")
+                        .push(IndexEntry { pcr, byte_offset: offset });
+                    last_pcr = Some(pcr);
+                }
+            }
+            offset += TS_PACKET_LEN as u64;
+        }
+
+        Ok(TsSeeker {
+            inner,
+            pcr_pid,
+            segments,
+        })
+    }
+
+    /// Seeks to the packet boundary at or immediately before `pcr`.
+    ///
+    /// The search is confined to whichever indexed segment contains a PCR
+    /// closest to (and not after) `pcr`; segments never span a
+    /// discontinuity or PCR wraparound.
+    pub fn seek_to_pcr(&mut self, pcr: Pcr) -> Result<()> {
+        let mut best: Option<&IndexEntry> = None;
+        for segment in &self.segments {
+            match segment.binary_search_by_key(&pcr, |e| e.pcr) {
+                Ok(i) => {
+                    best = Some(&segment[i]);
+                    break;
+                }
+                Err(0) => {}
+                Err(i) => {
+                    let candidate = &segment[i - 1];
+                    if best.map_or(true, |b| candidate.pcr > b.pcr) {
+                        best = Some(candidate);
+                    }
+                }
+            }
+        }
+        let entry = track_assert_some!(best, ErrorKind::InvalidInput, "No PCR at or before {}", pcr);
+        track_io!(self.inner.seek(SeekFrom::Start(entry.byte_offset)))?;
+        Ok(())
+    }
+
+    /// Seeks to the packet boundary at or immediately before `secs`
+    /// seconds into the (first segment of the) stream.
+    pub fn seek_to_secs(&mut self, secs: f64) -> Result<()> {
+        let first_pcr = self.segments
+            .iter()
+            .flat_map(|s| s.first())
+            .map(|e| e.pcr)
+            .next();
+        let first_pcr = track_assert_some!(first_pcr, ErrorKind::InvalidInput, "Empty PCR index");
+        let target = first_pcr + (secs * PCR_HZ as f64) as u64;
+        self.seek_to_pcr(target)
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Consumes this seeker, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Scans forward byte-by-byte from `start` for the first sync byte
+/// (`0x47`), so that streams whose current position doesn't already sit
+/// on a packet boundary can still be indexed instead of failing outright.
+fn find_first_sync_byte<R: Read>(reader: &mut R, start: u64) -> Result<u64> {
+    let mut byte = [0; 1];
+    let mut offset = start;
+    loop {
+        let n = track_io!(reader.read(&mut byte))?;
+        track_assert!(n > 0, ErrorKind::InvalidInput, "No sync byte found before EOF");
+        if byte[0] == 0x47 {
+            return Ok(offset);
+        }
+        offset += 1;
+    }
+}
+
+fn read_all_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        let n = track_io!(reader.read(&mut buf[read..]))?;
+        if n == 0 {
+            break;
+        }
+        read += n;
+    }
+    Ok(read)
+}
+
+/// Reads the PCR (and `discontinuity_indicator`) from a raw 188-byte TS
+/// packet, if its adaptation field carries one.
+fn read_pcr(packet: &[u8]) -> Result<Option<(Pcr, bool)>> {
+    let adaptation_field_control = (packet[3] >> 4) & 0b11;
+    if adaptation_field_control != 0b10 && adaptation_field_control != 0b11 {
+        return Ok(None);
+    }
+    track_assert!(packet.len() > 4, ErrorKind::InvalidInput, "Truncated TS packet");
+    let adaptation_field_len = packet[4] as usize;
+    if adaptation_field_len == 0 {
+        return Ok(None);
+    }
+    track_assert!(
+        5 + adaptation_field_len <= packet.len(),
+        ErrorKind::InvalidInput,
+        "Adaptation field overruns the packet"
+    );
+    let flags = packet[5];
+    let discontinuity_indicator = flags & 0b1000_0000 != 0;
+    let pcr_flag = flags & 0b0001_0000 != 0;
+    if !pcr_flag {
+        return Ok(None);
+    }
+    track_assert!(adaptation_field_len >= 7, ErrorKind::InvalidInput, "Adaptation field too short for a PCR");
+
+    let b = &packet[6..12];
+    let base = (u64::from(b[0]) << 25)
+        | (u64::from(b[1]) << 17)
+        | (u64::from(b[2]) << 9)
+        | (u64::from(b[3]) << 1)
+        | (u64::from(b[4]) >> 7);
+    let extension = (u64::from(b[4] & 0b0000_0001) << 8) | u64::from(b[5]);
+    let pcr = base * 300 + extension;
+    Ok(Some((pcr, discontinuity_indicator)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+
+    fn packet_with_pcr(pid: Pid, pcr: Pcr) -> [u8; TS_PACKET_LEN] {
+        let mut buf = [0xFF; TS_PACKET_LEN];
+        buf[0] = 0x47;
+        buf[1] = (pid.as_u16() >> 8) as u8 & 0b0001_1111;
+        buf[2] = (pid.as_u16() & 0xFF) as u8;
+        buf[3] = 0b0010_0000; // adaptation_field_control == adaptation field only
+        buf[4] = 183; // adaptation_field_length (fills the rest of the packet)
+        buf[5] = 0b0001_0000; // pcr_flag
+        let base = pcr / 300;
+        let extension = pcr % 300;
+        buf[6] = (base >> 25) as u8;
+        buf[7] = (base >> 17) as u8;
+        buf[8] = (base >> 9) as u8;
+        buf[9] = (base >> 1) as u8;
+        buf[10] = ((base & 1) as u8) << 7 | 0b0111_1110 | (extension >> 8) as u8;
+        buf[11] = (extension & 0xFF) as u8;
+        buf
+    }
+
+    #[test]
+    fn read_pcr_decodes_the_33_bit_base_and_9_bit_extension() {
+        let pid = Pid::new(0x100).unwrap();
+        let pcr = 12_345_678_901u64;
+        let buf = packet_with_pcr(pid, pcr);
+        let (decoded, discontinuity) = read_pcr(&buf).unwrap().unwrap();
+        assert_eq!(decoded, pcr);
+        assert!(!discontinuity);
+    }
+
+    #[test]
+    fn new_resyncs_to_the_first_sync_byte_instead_of_failing() {
+        let pid = Pid::new(0x100).unwrap();
+        let mut stream = vec![0x00, 0x00, 0x00]; // junk before the first packet
+        stream.extend_from_slice(&packet_with_pcr(pid, 27_000_000));
+
+        let seeker = TsSeeker::new(Cursor::new(stream), pid).unwrap();
+        assert_eq!(seeker.segments.len(), 1);
+        assert_eq!(seeker.segments[0].len(), 1);
+        assert_eq!(seeker.segments[0][0].byte_offset, 3);
+    }
+}
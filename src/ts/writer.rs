@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::Write;
+use byteorder::{BigEndian, WriteBytesExt};
+
+use {ErrorKind, Result};
+use ts::{ContinuityCounter, Pid, TsPacket, TsPayload};
+
+/// The size in bytes of a single TS packet.
+pub const TS_PACKET_LEN: usize = 188;
+
+/// Serializes `TsPacket`s to an underlying byte stream.
+///
+/// A continuity counter is tracked per PID and incremented automatically
+/// for every packet carrying a payload; packets without a payload (e.g.
+/// adaptation-field-only packets carrying just a PCR) are written with
+/// that PID's last-written value instead of advancing it, per spec.
+#[derive(Debug)]
+pub struct TsPacketWriter<W> {
+    inner: W,
+    // The continuity counter value most recently written for each PID, if
+    // any packet has been written on it yet.
+    continuity_counters: HashMap<Pid, ContinuityCounter>,
+}
+impl<W: Write> TsPacketWriter<W> {
+    /// Makes a new `TsPacketWriter` instance.
+    pub fn new(inner: W) -> Self {
+        TsPacketWriter {
+            inner,
+            continuity_counters: HashMap::new(),
+        }
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Writes a TS packet.
+    ///
+    /// The packet's continuity counter is overwritten with the
+    /// automatically tracked per-PID value before it is serialized: it
+    /// advances for packets carrying a payload, and holds at whatever was
+    /// last written for this PID for packets without one.
+    pub fn write_ts_packet(&mut self, packet: &TsPacket) -> Result<()> {
+        let mut buf = [0; TS_PACKET_LEN];
+        {
+            let mut writer = &mut buf[..];
+            track_io!(writer.write_u8(0x47))?; // sync_byte
+
+            let pid = packet.header.pid;
+            let byte1 = ((packet.header.transport_error_indicator as u8) << 7)
+                | ((packet.header.payload_unit_start_indicator as u8) << 6)
+                | ((packet.header.transport_priority as u8) << 5)
+                | ((pid.as_u16() >> 8) as u8 & 0b0001_1111);
+            track_io!(writer.write_u8(byte1))?;
+            track_io!(writer.write_u8((pid.as_u16() & 0b1111_1111) as u8))?;
+
+            let payload = packet.payload.as_ref().map(TsPayload::as_bytes);
+            let has_payload = payload.is_some();
+
+            // When the caller didn't supply an adaptation field and the
+            // payload doesn't exactly fill the remaining 184 bytes, a
+            // stuffing adaptation field must be synthesized to pad the
+            // packet; otherwise the leftover bytes would be read back as
+            // payload, since `adaptation_field_control` only has room to
+            // say "payload" or "adaptation field (+ payload)", not "short
+            // payload, zero-padded".
+            let space_after_header = TS_PACKET_LEN - 4;
+            let payload_len = payload.map_or(0, |p| p.len());
+            let needs_stuffing_field = packet.adaptation_field.is_none()
+                && has_payload
+                && payload_len < space_after_header;
+            let has_adaptation_field = packet.adaptation_field.is_some() || needs_stuffing_field;
+
+            // Advance *before* reading back for a payload-bearing packet
+            // (so a fresh PID's first packet reads as counter 0, and a
+            // PID's second payload-bearing packet reads as counter 1);
+            // packets without a payload just read back whatever was last
+            // written for this PID, without advancing it.
+            let continuity_counter = match self.continuity_counters.entry(pid) {
+                Entry::Occupied(mut e) => {
+                    if has_payload {
+                        e.get_mut().increment();
+                    }
+                    *e.get()
+                }
+                Entry::Vacant(e) => *e.insert(ContinuityCounter::new()),
+            };
+            let byte4 = (packet.header.transport_scrambling_control.as_u8() << 6)
+                | ((has_adaptation_field as u8) << 5)
+                | ((has_payload as u8) << 4)
+                | (continuity_counter.as_u8() & 0b0000_1111);
+            track_io!(writer.write_u8(byte4))?;
+
+            if let Some(ref af) = packet.adaptation_field {
+                track!(af.write_to(&mut writer))?;
+            } else if needs_stuffing_field {
+                let field_len = space_after_header - payload_len;
+                track_io!(writer.write_all(&stuffing_adaptation_field(field_len)))?;
+            }
+            if let Some(payload) = payload {
+                track_assert!(
+                    payload.len() <= writer.len(),
+                    ErrorKind::InvalidInput,
+                    "Payload does not fit in the remaining packet space"
+                );
+                track_io!(writer.write_all(payload))?;
+            }
+        }
+        track_io!(self.inner.write_all(&buf))?;
+        Ok(())
+    }
+
+    /// Consumes this writer, returning the underlying stream.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Builds a stuffing-only adaptation field occupying exactly `field_len`
+/// bytes (including its own `adaptation_field_length` byte), so that a
+/// payload shorter than the remaining packet space is padded out to a
+/// full 188-byte TS packet instead of leaving trailing bytes that a
+/// conformant reader would mistake for payload.
+fn stuffing_adaptation_field(field_len: usize) -> Vec<u8> {
+    let mut field = Vec::with_capacity(field_len);
+    let adaptation_field_length = (field_len - 1) as u8;
+    field.push(adaptation_field_length);
+    if adaptation_field_length > 0 {
+        field.push(0); // all adaptation field flags cleared
+        field.extend(vec![0xFF; (adaptation_field_length - 1) as usize]); // stuffing_byte
+    }
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ts::TsHeader;
+    use ts::payload::Bytes;
+
+    #[test]
+    fn stuffing_adaptation_field_fills_requested_length() {
+        for field_len in 1..=20 {
+            let field = stuffing_adaptation_field(field_len);
+            assert_eq!(field.len(), field_len);
+        }
+    }
+
+    #[test]
+    fn stuffing_adaptation_field_length_byte_excludes_itself() {
+        let field = stuffing_adaptation_field(5);
+        assert_eq!(field[0], 4);
+        assert_eq!(&field[1..], &[0, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn stuffing_adaptation_field_of_one_byte_is_just_the_length_field() {
+        assert_eq!(stuffing_adaptation_field(1), vec![0]);
+    }
+
+    fn packet_with_payload(pid: Pid, payload: &'static [u8]) -> TsPacket {
+        TsPacket {
+            header: TsHeader {
+                pid,
+                ..TsHeader::default()
+            },
+            adaptation_field: None,
+            payload: Some(TsPayload::Raw(Bytes::new(payload).unwrap())),
+        }
+    }
+
+    // A packet carrying neither an adaptation field nor a payload isn't
+    // itself meaningful on the wire, but it exercises the `has_payload ==
+    // false` branch of the continuity counter logic under test without
+    // needing to construct a real `AdaptationField`.
+    fn packet_without_payload(pid: Pid) -> TsPacket {
+        TsPacket {
+            header: TsHeader {
+                pid,
+                ..TsHeader::default()
+            },
+            adaptation_field: None,
+            payload: None,
+        }
+    }
+
+    #[test]
+    fn no_payload_packets_reuse_the_pids_counter_without_advancing_it() {
+        let pid = Pid::new(0x101).unwrap();
+        let mut out = Vec::new();
+        let mut writer = TsPacketWriter::new(&mut out);
+
+        writer.write_ts_packet(&packet_with_payload(pid, &[0xAA; 10])).unwrap();
+        writer.write_ts_packet(&packet_without_payload(pid)).unwrap();
+        writer.write_ts_packet(&packet_without_payload(pid)).unwrap();
+        writer.write_ts_packet(&packet_with_payload(pid, &[0xBB; 10])).unwrap();
+
+        let counters: Vec<u8> = out
+            .chunks(TS_PACKET_LEN)
+            .map(|packet| packet[3] & 0b0000_1111)
+            .collect();
+        // The two no-payload packets in the middle hold the counter
+        // steady at the value the first payload-bearing packet used;
+        // the final payload-bearing packet advances it by exactly one.
+        assert_eq!(counters[1], counters[0]);
+        assert_eq!(counters[2], counters[0]);
+        assert_eq!(counters[3], (counters[0] + 1) & 0b0000_1111);
+    }
+}